@@ -1,27 +1,43 @@
 //! Account system.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::{Deref, DerefMut},
+    time::SystemTime,
 };
 
+use argon2::{
+    password_hash::{PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
 use lettre::{transport::smtp, AsyncSmtpTransport};
 use libaccount::{Academy, House};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
 use crate::{config, Error, TestCx};
 
-use self::verify::{Captcha, VerifyCx, VerifyVariant};
+use self::{
+    token::ApiKey,
+    verify::{Captcha, VerifyCx, VerifyVariant},
+};
 
+pub mod permission;
+pub mod token;
 pub mod verify;
 
 /// A permission group of an account.
+///
+/// Which permissions each variant implies (its "containing permissions")
+/// is no longer fixed by this enum: it's expanded from the role graph in
+/// [`permission`] at startup. The lists below describe the built-in
+/// default role set, used when no `permissions` config is provided.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[non_exhaustive]
 pub enum Permission {
     /// Post postings.
     ///
-    /// # Containing permissions
+    /// # Containing permissions (default roles)
     ///
     /// - [`Self::GetPubPost`]
     Post,
@@ -29,13 +45,13 @@ pub enum Permission {
     GetPubPost,
     /// View, approve or reject posts.
     ///
-    /// # Containing permissions
+    /// # Containing permissions (default roles)
     ///
     /// - [`Self::GetPubPost`]
     ReviewPost,
     /// Remove posts.
     ///
-    /// # Containing permissions
+    /// # Containing permissions (default roles)
     ///
     /// - [`Self::GetPubPost`]
     /// - [`Self::ReviewPost`]
@@ -44,14 +60,14 @@ pub enum Permission {
     /// Appends or removes permissions from
     /// an account.
     ///
-    /// # Containing permissions
+    /// # Containing permissions (default roles)
     ///
     /// - [`Self::ViewSimpleAccount`]
     /// - [`Self::ViewFullAccount`]
     SetPermissions,
     /// Gets full information of an account.
     ///
-    /// # Containing permissions
+    /// # Containing permissions (default roles)
     ///
     /// - [`Self::ViewSimpleAccount`]
     ViewFullAccount,
@@ -60,7 +76,7 @@ pub enum Permission {
 
     /// Manage notifications.
     ///
-    /// # Containing permissions
+    /// # Containing permissions (default roles)
     ///
     /// - [`Self::GetPubNotifications`]
     ManageNotifications,
@@ -77,36 +93,12 @@ pub enum Permission {
 impl libaccount::Permission for Permission {
     #[inline]
     fn default_set() -> std::collections::HashSet<Self> {
-        [
-            Self::Post,
-            Self::GetPubPost,
-            Self::ViewSimpleAccount,
-            Self::UploadResource,
-            Self::GetPubNotifications,
-        ]
-        .into()
+        permission::default_set()
     }
 
     #[inline]
     fn contains(&self, permission: &Self) -> bool {
-        matches!(
-            (self, permission),
-            (Permission::Post, Permission::GetPubPost)
-                | (
-                    Permission::SetPermissions,
-                    Permission::ViewFullAccount | Permission::ViewSimpleAccount
-                )
-                | (Permission::ViewFullAccount, Permission::ViewSimpleAccount)
-                | (Permission::ReviewPost, Permission::GetPubPost)
-                | (
-                    Permission::RemovePost,
-                    Permission::GetPubPost | Permission::ReviewPost
-                )
-                | (
-                    Permission::ManageNotifications,
-                    Permission::GetPubNotifications
-                )
-        )
+        permission::contains(*self, *permission)
     }
 }
 
@@ -122,6 +114,8 @@ pub enum Tag {
     House(House),
     /// An academy.
     Academy(Academy),
+    /// Email-based two-factor authentication is enabled for this account.
+    TwoFactorEnabled,
 }
 
 /// The entry of a [`Tag`].
@@ -135,6 +129,8 @@ pub enum TagEntry {
     House,
     /// An academy.
     Academy,
+    /// Whether email-based two-factor authentication is enabled.
+    TwoFactorEnabled,
 }
 
 impl libaccount::tag::Tag for Tag {
@@ -147,6 +143,7 @@ impl libaccount::tag::Tag for Tag {
             Tag::Department(_) => TagEntry::Department,
             Tag::House(_) => TagEntry::House,
             Tag::Academy(_) => TagEntry::Academy,
+            Tag::TwoFactorEnabled => TagEntry::TwoFactorEnabled,
         }
     }
 }
@@ -188,17 +185,62 @@ impl libaccount::tag::UserDefinableEntry for TagEntry {
 pub struct Ext {
     /// Verify sessions.
     verifies: HashMap<VerifyVariant, VerifyCx>,
+    /// The new email address pending confirmation by a
+    /// [`VerifyVariant::ChangeEmail`] session.
+    pending_email: Option<String>,
+    /// Minted application tokens.
+    #[serde(default)]
+    api_keys: Vec<ApiKey>,
+}
+
+/// The wire shape of [`Ext`] before `pending_email` was added, for decoding
+/// [`dmds::Data`] version 1.
+///
+/// Bincode is positional, not self-describing: a v1 blob only ever wrote
+/// `verifies`, so it must be deserialized into this narrower shape rather
+/// than directly into the current [`Ext`].
+#[derive(Deserialize)]
+struct ExtV1 {
+    verifies: HashMap<VerifyVariant, VerifyCx>,
+}
+
+impl From<ExtV1> for Ext {
+    #[inline]
+    fn from(v1: ExtV1) -> Self {
+        Self {
+            verifies: v1.verifies,
+            pending_email: None,
+            api_keys: Vec::new(),
+        }
+    }
+}
+
+/// The wire shape of [`Ext`] before `api_keys` was added, for decoding
+/// [`dmds::Data`] versions 2 and 3.
+#[derive(Deserialize)]
+struct ExtV2 {
+    verifies: HashMap<VerifyVariant, VerifyCx>,
+    pending_email: Option<String>,
+}
+
+impl From<ExtV2> for Ext {
+    #[inline]
+    fn from(v2: ExtV2) -> Self {
+        Self {
+            verifies: v2.verifies,
+            pending_email: v2.pending_email,
+            api_keys: Vec::new(),
+        }
+    }
 }
 
 /// A verified account.
 ///
 /// # Verify Sessions
 ///
-/// Sessions that requires email verifying, like reseting password,
-/// are verify sessions. (See [`VerifyVariant`])
+/// Sessions that requires email verifying, like reseting password
+/// or changing the login email, are verify sessions. (See [`VerifyVariant`])
 /// Verify sessions are stored in external data as [`Ext`].
-///
-/// Currently, the only verify session is reset password.
 #[derive(Debug)]
 pub struct Account {
     /// The inner account.
@@ -228,22 +270,132 @@ impl Account {
             .await
     }
 
-    /// Resets the password with given new password.
+    /// Resets the password with given new password, hashed with Argon2id
+    /// using `kdf`'s cost parameters.
     ///
     /// # Errors
     ///
     /// - Errors if the captcha is incorrect.
+    /// - Errors if hashing the new password failed.
     #[inline]
-    pub fn reset_password<T>(&mut self, captcha: Captcha, new_password: T) -> Result<(), Error>
+    pub fn reset_password<T>(
+        &mut self,
+        captcha: Captcha,
+        new_password: T,
+        kdf: &config::Kdf,
+    ) -> Result<(), Error>
     where
         T: AsRef<str>,
     {
         self.do_verify(VerifyVariant::ResetPassword, captcha)?;
-        self.inner.set_password(new_password);
+        self.inner.set_password_hash(hash_password(kdf, new_password)?);
+        Ok(())
+    }
+
+    /// Requests to change the login email and sends a captcha to the
+    /// *new* address.
+    ///
+    /// The new address only replaces the current one once it is confirmed
+    /// with [`Self::change_email`], so a failed or abandoned verification
+    /// leaves the original, already-verified email intact. The stored
+    /// pending address is canonicalized per `normalization`; the captcha
+    /// is still sent to the address as typed.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if `new_email` is not ended with `@pkuschool.edu.cn`
+    /// or `@i.pkuschool.edu.cn`.
+    /// - Errors if the difference between the last request time
+    /// and the current time is no more than 10 minutes.
+    /// - Errors if the email send failed.
+    #[inline]
+    pub async fn req_change_email<E>(
+        &mut self,
+        new_email: String,
+        normalization: &EmailNormalizationConfig,
+        config: &config::SMTP,
+        transport: &AsyncSmtpTransport<E>,
+        cx: &TestCx,
+    ) -> Result<(), Error>
+    where
+        E: lettre::Executor,
+        AsyncSmtpTransport<E>: lettre::AsyncTransport<Error = smtp::Error>,
+    {
+        validate_email_domain(&new_email)?;
+        let canonical = normalize_email(&new_email, normalization);
+        let to = new_email.parse()?;
+        // Only commit `pending_email` once the request itself succeeds, so a
+        // rejected request (e.g. rate-limited) can't leave behind an
+        // unconfirmed address for a still-valid captcha from an earlier
+        // session to later commit.
+        self.req_verify_to(VerifyVariant::ChangeEmail, to, config, transport, cx)
+            .await?;
+        self.inner.ext_mut().pending_email = Some(canonical);
+        Ok(())
+    }
+
+    /// Confirms the pending new address and commits it as the login email.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if there's no pending [`VerifyVariant::ChangeEmail`] session.
+    /// - Errors if the captcha is incorrect.
+    pub fn change_email(&mut self, captcha: Captcha) -> Result<(), Error> {
+        let new_email = self
+            .inner
+            .ext()
+            .pending_email
+            .clone()
+            .ok_or(Error::VerifySessionNotFound(VerifyVariant::ChangeEmail))?;
+        self.do_verify(VerifyVariant::ChangeEmail, captcha)?;
+        self.inner.ext_mut().pending_email = None;
+        self.inner.set_email(new_email);
         Ok(())
     }
 
-    /// Requests a verify session and sends an email to user.
+    /// Whether email-based two-factor authentication is enabled for this
+    /// account, i.e. whether a caller should demand
+    /// [`Self::verify_login_2fa`] after a successful password check.
+    #[inline]
+    pub fn two_factor_enabled(&self) -> bool {
+        self.inner.tags().contains(&Tag::TwoFactorEnabled)
+    }
+
+    /// Requests a login two-factor session and sends a captcha to the
+    /// account's own email.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if the difference between the last request time
+    /// and the current time is no more than 10 minutes.
+    /// - Errors if the email send failed.
+    #[inline]
+    pub async fn req_login_2fa<E>(
+        &mut self,
+        config: &config::SMTP,
+        transport: &AsyncSmtpTransport<E>,
+        cx: &TestCx,
+    ) -> Result<(), Error>
+    where
+        E: lettre::Executor,
+        AsyncSmtpTransport<E>: lettre::AsyncTransport<Error = smtp::Error>,
+    {
+        self.req_verify(VerifyVariant::LoginTwoFactor, config, transport, cx)
+            .await
+    }
+
+    /// Verifies the second factor of a login with the given captcha.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if the captcha is incorrect.
+    #[inline]
+    pub fn verify_login_2fa(&mut self, captcha: Captcha) -> Result<(), Error> {
+        self.do_verify(VerifyVariant::LoginTwoFactor, captcha)
+    }
+
+    /// Requests a verify session, sending its captcha to the account's own
+    /// email.
     ///
     /// # Errors
     ///
@@ -262,6 +414,28 @@ impl Account {
         AsyncSmtpTransport<E>: lettre::AsyncTransport<Error = smtp::Error>,
     {
         let to = self.inner.email().parse()?;
+        self.req_verify_to(variant, to, config, transport, cx).await
+    }
+
+    /// Requests a verify session and sends its captcha to `to`.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if the difference between the last request time
+    /// and the current time is no more than 10 minutes.
+    /// - Errors if the email send failed.
+    async fn req_verify_to<E>(
+        &mut self,
+        variant: VerifyVariant,
+        to: lettre::message::Mailbox,
+        config: &config::SMTP,
+        transport: &AsyncSmtpTransport<E>,
+        cx: &TestCx,
+    ) -> Result<(), Error>
+    where
+        E: lettre::Executor,
+        AsyncSmtpTransport<E>: lettre::AsyncTransport<Error = smtp::Error>,
+    {
         let ext = self.inner.ext_mut();
         if let Some(cx) = ext.verifies.get_mut(&variant) {
             cx.update()?;
@@ -293,6 +467,187 @@ impl Account {
             Err(Error::CaptchaIncorrect)
         }
     }
+
+    /// The Argon2id parameters a client should use to derive this account's
+    /// password verifier before authenticating, parsed from the account's
+    /// own stored hash.
+    ///
+    /// Returns [`KdfParams::Legacy`] for accounts that haven't been
+    /// migrated to Argon2id yet (see [`Self::needs_password_rehash`]).
+    #[inline]
+    pub fn prelogin(&self) -> KdfParams {
+        kdf_params_of(self.inner.password_hash())
+    }
+
+    /// Whether this account's password hash predates the Argon2id
+    /// migration and should be rehashed on next successful login.
+    #[inline]
+    pub fn needs_password_rehash(&self) -> bool {
+        matches!(self.prelogin(), KdfParams::Legacy)
+    }
+
+    /// Rehashes the current password with Argon2id using `kdf`'s cost
+    /// parameters, migrating the account forward.
+    ///
+    /// Call this with the just-verified password after a successful login
+    /// for which [`Self::needs_password_rehash`] returns `true`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if hashing the password failed.
+    pub fn migrate_password_hash<T>(&mut self, password: T, kdf: &config::Kdf) -> Result<(), Error>
+    where
+        T: AsRef<str>,
+    {
+        self.inner.set_password_hash(hash_password(kdf, password)?);
+        Ok(())
+    }
+
+    /// Mints a new application token scoped to `scope`, which must be a
+    /// subset of this account's own permissions, optionally expiring at
+    /// `expires_at`.
+    ///
+    /// Returns the token's id and its plaintext secret. The secret is
+    /// never stored and can't be recovered later, only reissued by
+    /// minting a new token.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if `scope` isn't a subset of this account's permissions.
+    /// - Errors if hashing the secret failed.
+    pub fn mint_key(
+        &mut self,
+        scope: HashSet<Permission>,
+        expires_at: Option<SystemTime>,
+        kdf: &config::Kdf,
+    ) -> Result<(String, String), Error> {
+        if !scope.is_subset(self.inner.permissions()) {
+            return Err(Error::PermissionScopeTooBroad);
+        }
+        let (key, secret) = token::mint(scope, expires_at, kdf)?;
+        let id = key.id().to_string();
+        self.inner.ext_mut().api_keys.push(key);
+        Ok((id, secret))
+    }
+
+    /// Verifies `secret` against the token `id`.
+    ///
+    /// The Argon2 comparison always runs, even when `id` doesn't name an
+    /// existing token, so looking up an unknown id takes comparable time to
+    /// checking a wrong secret against a real one.
+    ///
+    /// On success, returns the intersection of the token's scope with this
+    /// account's *live* permissions, so a permission revoked from the
+    /// account after the token was minted is excluded even though it's
+    /// still recorded in the token's stored scope.
+    ///
+    /// # Errors
+    ///
+    /// - Errors if no token with `id` exists, it has expired, or `secret`
+    /// is incorrect.
+    pub fn verify_key(
+        &mut self,
+        id: &str,
+        secret: &str,
+        kdf: &config::Kdf,
+    ) -> Result<HashSet<Permission>, Error> {
+        let permissions = self.inner.permissions().clone();
+        let key = match self.inner.ext_mut().api_keys.iter_mut().find(|key| key.id() == id) {
+            Some(key) if token::verify(key, secret) => key,
+            Some(_) => return Err(Error::ApiKeyNotFound),
+            None => {
+                token::verify_dummy(secret, kdf);
+                return Err(Error::ApiKeyNotFound);
+            }
+        };
+        token::mark_used(key);
+        Ok(key.scope().intersection(&permissions).copied().collect())
+    }
+
+    /// Enumerates this account's application tokens.
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = &ApiKey> {
+        self.inner.ext().api_keys.iter()
+    }
+
+    /// Revokes the application token with the given id.
+    ///
+    /// # Errors
+    ///
+    /// Errors if no token with `id` exists.
+    pub fn revoke_key(&mut self, id: &str) -> Result<(), Error> {
+        let keys = &mut self.inner.ext_mut().api_keys;
+        let len = keys.len();
+        keys.retain(|key| key.id() != id);
+        if keys.len() == len {
+            Err(Error::ApiKeyNotFound)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The Argon2id parameters used for an account's password hash, or
+/// [`KdfParams::Legacy`] if `hash` predates the Argon2id migration and
+/// can't be parsed as one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KdfParams {
+    /// The account's password is hashed with Argon2id using these
+    /// parameters.
+    Argon2id {
+        /// Memory cost, in KiB.
+        memory_cost: u32,
+        /// Time cost (number of iterations).
+        time_cost: u32,
+        /// Degree of parallelism.
+        parallelism: u32,
+    },
+    /// The account's password predates the Argon2id migration; the client
+    /// must submit the raw password so it can be verified and rehashed.
+    Legacy,
+}
+
+/// Parses the Argon2id parameters out of a stored password hash, falling
+/// back to [`KdfParams::Legacy`] if it isn't one.
+fn kdf_params_of(hash: &str) -> KdfParams {
+    argon2::PasswordHash::new(hash)
+        .ok()
+        .and_then(|hash| {
+            if hash.algorithm.as_str() != "argon2id" {
+                return None;
+            }
+            let params = Params::try_from(&hash).ok()?;
+            Some(KdfParams::Argon2id {
+                memory_cost: params.m_cost(),
+                time_cost: params.t_cost(),
+                parallelism: params.p_cost(),
+            })
+        })
+        .unwrap_or(KdfParams::Legacy)
+}
+
+/// Hashes `password` with Argon2id using `kdf`'s cost parameters, returning
+/// the encoded PHC hash string to store.
+///
+/// # Errors
+///
+/// Errors if the configured parameters are invalid or hashing failed.
+pub(crate) fn hash_password<T>(kdf: &config::Kdf, password: T) -> Result<String, Error>
+where
+    T: AsRef<str>,
+{
+    let params = Params::new(kdf.memory_cost, kdf.time_cost, kdf.parallelism, None)
+        .map_err(|err| Error::Kdf(err.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut salt = vec![0; kdf.salt_len];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).map_err(|err| Error::Kdf(err.to_string()))?;
+
+    Ok(argon2
+        .hash_password(password.as_ref().as_bytes(), &salt)
+        .map_err(|err| Error::Kdf(err.to_string()))?
+        .to_string())
 }
 
 impl From<libaccount::Account<Tag, Ext>> for Account {
@@ -304,7 +659,12 @@ impl From<libaccount::Account<Tag, Ext>> for Account {
 
 impl dmds::Data for Account {
     const DIMS: usize = 1;
-    const VERSION: u32 = 1;
+    /// - Bumped to 2 when [`Ext`] gained `pending_email`.
+    /// - Bumped to 3 for the Argon2id migration: the wire format is
+    /// unchanged, but accounts loaded before this version may still carry a
+    /// pre-Argon2id password hash (see [`Account::needs_password_rehash`]).
+    /// - Bumped to 4 since [`Ext`] gained the `api_keys` field.
+    const VERSION: u32 = 4;
 
     #[inline]
     fn dim(&self, dim: usize) -> u64 {
@@ -317,6 +677,24 @@ impl dmds::Data for Account {
     fn decode<B: bytes::Buf>(version: u32, dims: &[u64], buf: B) -> std::io::Result<Self> {
         match version {
             1 => {
+                let mut inner: libaccount::Account<Tag, ExtV1> =
+                    bincode::deserialize_from(buf.reader())
+                        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+                unsafe { inner.initialize_id(dims[0]) };
+                Ok(Self {
+                    inner: inner.map_ext(Ext::from),
+                })
+            }
+            2 | 3 => {
+                let mut inner: libaccount::Account<Tag, ExtV2> =
+                    bincode::deserialize_from(buf.reader())
+                        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+                unsafe { inner.initialize_id(dims[0]) };
+                Ok(Self {
+                    inner: inner.map_ext(Ext::from),
+                })
+            }
+            4 => {
                 let mut inner: libaccount::Account<Tag, Ext> =
                     bincode::deserialize_from(buf.reader())
                         .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
@@ -350,28 +728,101 @@ impl DerefMut for Account {
     }
 }
 
+/// Email domains allowed to register or log in.
+const ALLOWED_EMAIL_DOMAINS: [&str; 2] = ["@pkuschool.edu.cn", "@i.pkuschool.edu.cn"];
+
+/// Checks that `email` is ended with an allowed domain.
+///
+/// # Errors
+///
+/// Errors if `email` is not ended with `@pkuschool.edu.cn`
+/// or `@i.pkuschool.edu.cn`.
+#[inline]
+fn validate_email_domain(email: &str) -> Result<(), Error> {
+    let email = email.to_lowercase();
+    if ALLOWED_EMAIL_DOMAINS.iter().any(|domain| email.ends_with(domain)) {
+        Ok(())
+    } else {
+        Err(Error::InvalidEmailDomain)
+    }
+}
+
+/// Per-domain email canonicalization rules, configured in `config`, keyed
+/// by lowercased domain (e.g. `"i.pkuschool.edu.cn"`).
+pub type EmailNormalizationConfig = HashMap<String, EmailNormalization>;
+
+/// How to canonicalize the local part of an email address for a domain.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct EmailNormalization {
+    /// Lowercase the local part before hashing/deduplicating.
+    #[serde(default)]
+    pub lowercase_local: bool,
+    /// Strip a `+subaddress` suffix (`local+anything` -> `local`) from the
+    /// local part.
+    #[serde(default)]
+    pub strip_subaddress: bool,
+}
+
+/// Canonicalizes `email` for deduplication: the domain is always
+/// lowercased, and the local part is transformed per `rules`' entry for
+/// that domain, if any.
+///
+/// The result feeds `email_hash` and the stored email; the address as
+/// typed by the user is kept separately so verification mail still reaches
+/// exactly what they entered.
+fn normalize_email(email: &str, rules: &EmailNormalizationConfig) -> String {
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return email.to_string();
+    };
+    let domain = domain.to_lowercase();
+    let rule = rules.get(&domain).copied().unwrap_or_default();
+
+    let mut local = local.to_string();
+    if rule.strip_subaddress {
+        if let Some((base, _tag)) = local.split_once('+') {
+            local = base.to_string();
+        }
+    }
+    if rule.lowercase_local {
+        local = local.to_lowercase();
+    }
+
+    format!("{local}@{domain}")
+}
+
 /// An unverified account.
 #[derive(Debug)]
 pub struct Unverified {
-    /// The inner unverified account.
+    /// The inner unverified account, keyed by the canonicalized email.
     inner: libaccount::Unverified<VerifyCx>,
+    /// The address as typed by the user, used only to send the activation
+    /// captcha.
+    original_email: String,
 }
 
 impl Unverified {
     /// Creates a new unverified account.
     ///
+    /// `email` is canonicalized per `normalization` before being hashed and
+    /// stored, so e.g. `john+tag@pkuschool.edu.cn` and `John@...` collide
+    /// with `john@...` instead of registering as distinct accounts; the
+    /// captcha is still sent to the address as typed.
+    ///
     /// # Errors
     ///
     /// - Errors if email is not ended with `@pkuschool.edu.cn`
     /// or `@i.pkuschool.edu.cn`.
     #[inline]
-    pub fn new(email: String) -> Result<Self, Error> {
+    pub fn new(email: String, normalization: &EmailNormalizationConfig) -> Result<Self, Error> {
+        validate_email_domain(&email)?;
+        let canonical = normalize_email(&email, normalization);
         Ok(Self {
             inner: libaccount::Unverified::new(
-                email,
+                canonical,
                 VerifyCx::new(),
                 siphasher::sip::SipHasher24::new(),
             )?,
+            original_email: email,
         })
     }
 
@@ -392,7 +843,7 @@ impl Unverified {
         E: lettre::Executor,
         AsyncSmtpTransport<E>: lettre::AsyncTransport<Error = smtp::Error>,
     {
-        let to = self.inner.email().parse()?;
+        let to = self.original_email.parse()?;
         self.inner
             .ext_mut()
             .send_email(config, to, "account activation", transport, cx)
@@ -402,7 +853,9 @@ impl Unverified {
 
 impl dmds::Data for Unverified {
     const DIMS: usize = 1;
-    const VERSION: u32 = 1;
+    /// Bumped to 2 when `original_email` was added alongside the
+    /// canonicalized inner account.
+    const VERSION: u32 = 2;
 
     #[inline]
     fn dim(&self, dim: usize) -> u64 {
@@ -419,7 +872,21 @@ impl dmds::Data for Unverified {
                     bincode::deserialize_from(buf.reader())
                         .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
                 unsafe { inner.initialize_email_hash(dims[0]) };
-                Ok(Self { inner })
+                let original_email = inner.email().to_string();
+                Ok(Self {
+                    inner,
+                    original_email,
+                })
+            }
+            2 => {
+                let (original_email, mut inner): (String, libaccount::Unverified<VerifyCx>) =
+                    bincode::deserialize_from(buf.reader())
+                        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+                unsafe { inner.initialize_email_hash(dims[0]) };
+                Ok(Self {
+                    inner,
+                    original_email,
+                })
             }
             _ => unreachable!("unsupported data version {version}"),
         }
@@ -427,7 +894,7 @@ impl dmds::Data for Unverified {
 
     #[inline]
     fn encode<B: bytes::BufMut>(&self, buf: B) -> std::io::Result<()> {
-        bincode::serialize_into(buf.writer(), &self.inner)
+        bincode::serialize_into(buf.writer(), &(&self.original_email, &self.inner))
             .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
     }
 }
@@ -458,6 +925,10 @@ impl From<Unverified> for libaccount::Unverified<VerifyCx> {
 impl From<libaccount::Unverified<VerifyCx>> for Unverified {
     #[inline]
     fn from(value: libaccount::Unverified<VerifyCx>) -> Self {
-        Self { inner: value }
+        let original_email = value.email().to_string();
+        Self {
+            inner: value,
+            original_email,
+        }
     }
 }
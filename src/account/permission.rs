@@ -0,0 +1,268 @@
+//! Data-driven, hierarchical permission roles.
+//!
+//! [`Permission`] variants remain the leaf capabilities a [`super::Tag`] can
+//! grant, but which leaves a permission "contains" - i.e. which other leaves
+//! it also implies - is no longer a hand-written table. Instead it comes
+//! from a role graph read from `config`: each role grants a set of leaf
+//! permissions (plain names or `namespace.*` wildcards) and may list parent
+//! roles it inherits all grants from. At startup the graph is expanded into
+//! a flattened closure per role so [`contains`] stays an O(1) lookup.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::Permission;
+use crate::Error;
+
+/// A single role definition, as read from `config`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoleConfig {
+    /// Leaf permissions, or `namespace.*` wildcards, this role grants
+    /// directly.
+    #[serde(default)]
+    pub grants: Vec<String>,
+    /// Other roles this role inherits all grants from.
+    #[serde(default)]
+    pub parents: Vec<String>,
+}
+
+/// The `permissions` section of `config`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PermissionsConfig {
+    /// Role name to definition.
+    #[serde(default)]
+    pub roles: HashMap<String, RoleConfig>,
+    /// Grant entries composing the default permission set of a fresh
+    /// account.
+    #[serde(default)]
+    pub default: Vec<String>,
+}
+
+/// The namespaced key of a [`Permission`], matched against grant entries
+/// and `namespace.*` wildcards.
+fn key(permission: Permission) -> &'static str {
+    match permission {
+        Permission::Post => "post.create",
+        Permission::GetPubPost => "post.get",
+        Permission::ReviewPost => "post.review",
+        Permission::RemovePost => "post.remove",
+        Permission::SetPermissions => "account.set_permissions",
+        Permission::ViewFullAccount => "account.view_full",
+        Permission::ViewSimpleAccount => "account.view_simple",
+        Permission::ManageNotifications => "notification.manage",
+        Permission::GetPubNotifications => "notification.get_pub",
+        Permission::UploadResource => "resource.upload",
+        Permission::Maintain => "system.maintain",
+    }
+}
+
+/// All known permission variants, for resolving wildcard grants.
+const ALL: [Permission; 11] = [
+    Permission::Post,
+    Permission::GetPubPost,
+    Permission::ReviewPost,
+    Permission::RemovePost,
+    Permission::SetPermissions,
+    Permission::ViewFullAccount,
+    Permission::ViewSimpleAccount,
+    Permission::ManageNotifications,
+    Permission::GetPubNotifications,
+    Permission::UploadResource,
+    Permission::Maintain,
+];
+
+/// Resolves a grant entry (`"post.review"` or `"post.*"`) to the
+/// [`Permission`]s it names.
+fn resolve_grant(grant: &str) -> impl Iterator<Item = Permission> + '_ {
+    ALL.into_iter().filter(move |p| match grant.strip_suffix(".*") {
+        Some(namespace) => key(*p)
+            .strip_prefix(namespace)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => key(*p) == grant,
+    })
+}
+
+/// The built-in role set, one role per [`Permission`] variant, mirroring the
+/// previously hardcoded [`contains`] table. Used when no `permissions`
+/// config is provided.
+fn default_roles() -> HashMap<String, RoleConfig> {
+    fn role(grants: &[&str], parents: &[&str]) -> RoleConfig {
+        RoleConfig {
+            grants: grants.iter().map(|s| s.to_string()).collect(),
+            parents: parents.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+    [
+        ("post.create", role(&["post.create"], &["post.get"])),
+        ("post.get", role(&["post.get"], &[])),
+        ("post.review", role(&["post.review"], &["post.get"])),
+        (
+            "post.remove",
+            role(&["post.remove"], &["post.get", "post.review"]),
+        ),
+        (
+            "account.set_permissions",
+            role(
+                &["account.set_permissions"],
+                &["account.view_full", "account.view_simple"],
+            ),
+        ),
+        (
+            "account.view_full",
+            role(&["account.view_full"], &["account.view_simple"]),
+        ),
+        ("account.view_simple", role(&["account.view_simple"], &[])),
+        (
+            "notification.manage",
+            role(&["notification.manage"], &["notification.get_pub"]),
+        ),
+        (
+            "notification.get_pub",
+            role(&["notification.get_pub"], &[]),
+        ),
+        ("resource.upload", role(&["resource.upload"], &[])),
+        ("system.maintain", role(&["system.maintain"], &[])),
+    ]
+    .into_iter()
+    .map(|(name, role)| (name.to_string(), role))
+    .collect()
+}
+
+/// The built-in default permission set, used when no `permissions` config
+/// is provided.
+fn default_default_grants() -> Vec<String> {
+    ["post.create", "post.get", "account.view_simple", "resource.upload", "notification.get_pub"]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// The precomputed closure of each role: role name to the full set of
+/// permissions it grants, including everything inherited from its parents.
+static ROLE_CLOSURES: OnceLock<HashMap<String, HashSet<Permission>>> = OnceLock::new();
+
+/// The precomputed closure of each [`Permission`], used by [`contains`].
+static PERMISSION_CLOSURES: OnceLock<HashMap<Permission, HashSet<Permission>>> = OnceLock::new();
+
+/// The default permission set of a fresh account.
+static DEFAULT_SET: OnceLock<HashSet<Permission>> = OnceLock::new();
+
+/// Expands `roles` into a flattened closure per role, transitively unioning
+/// parents' grants.
+///
+/// # Errors
+///
+/// - Errors if a role lists an undefined parent role.
+/// - Errors if the role graph contains a cycle.
+fn expand(roles: &HashMap<String, RoleConfig>) -> Result<HashMap<String, HashSet<Permission>>, Error> {
+    /// Visit state for cycle detection during the depth-first expansion.
+    #[derive(PartialEq)]
+    enum State {
+        InProgress,
+        Done,
+    }
+
+    fn visit(
+        name: &str,
+        roles: &HashMap<String, RoleConfig>,
+        state: &mut HashMap<String, State>,
+        closures: &mut HashMap<String, HashSet<Permission>>,
+    ) -> Result<(), Error> {
+        match state.get(name) {
+            Some(State::Done) => return Ok(()),
+            Some(State::InProgress) => return Err(Error::PermissionRoleCycle(name.to_string())),
+            None => {}
+        }
+        let def = roles
+            .get(name)
+            .ok_or_else(|| Error::UndefinedPermissionRole(name.to_string()))?;
+        state.insert(name.to_string(), State::InProgress);
+
+        let mut set: HashSet<Permission> = def.grants.iter().flat_map(|g| resolve_grant(g)).collect();
+        for parent in &def.parents {
+            visit(parent, roles, state, closures)?;
+            set.extend(closures[parent.as_str()].iter().copied());
+        }
+
+        state.insert(name.to_string(), State::Done);
+        closures.insert(name.to_string(), set);
+        Ok(())
+    }
+
+    let mut state = HashMap::new();
+    let mut closures = HashMap::new();
+    for name in roles.keys() {
+        visit(name, roles, &mut state, &mut closures)?;
+    }
+    Ok(closures)
+}
+
+/// Loads the role graph from `config`, falling back to the built-in default
+/// role set if `config` is `None`, and precomputes every role's and every
+/// [`Permission`]'s closure.
+///
+/// Must be called once at startup, before any [`contains`] or
+/// [`default_set`] call.
+///
+/// # Errors
+///
+/// - Errors if a role lists an undefined parent role.
+/// - Errors if the role graph contains a cycle.
+pub fn init(config: Option<&PermissionsConfig>) -> Result<(), Error> {
+    let roles = config.map(|c| &c.roles);
+    let owned_roles;
+    let roles = match roles.filter(|r| !r.is_empty()) {
+        Some(roles) => roles,
+        None => {
+            owned_roles = default_roles();
+            &owned_roles
+        }
+    };
+    let role_closures = expand(roles)?;
+
+    let permission_closures = ALL
+        .into_iter()
+        .map(|p| {
+            let set = role_closures.get(key(p)).cloned().unwrap_or_else(|| [p].into());
+            (p, set)
+        })
+        .collect();
+
+    let default_grants = config
+        .map(|c| c.default.as_slice())
+        .filter(|d| !d.is_empty())
+        .map(|d| d.to_vec())
+        .unwrap_or_else(default_default_grants);
+    let default_set = default_grants.iter().flat_map(|g| resolve_grant(g)).collect();
+
+    let _ = ROLE_CLOSURES.set(role_closures);
+    let _ = PERMISSION_CLOSURES.set(permission_closures);
+    let _ = DEFAULT_SET.set(default_set);
+    Ok(())
+}
+
+/// Whether `permission` implies `other`, per the precomputed role closures.
+///
+/// Falls back to plain equality if [`init`] was never called.
+#[inline]
+pub fn contains(permission: Permission, other: Permission) -> bool {
+    PERMISSION_CLOSURES
+        .get()
+        .and_then(|closures| closures.get(&permission))
+        .map_or(permission == other, |closure| closure.contains(&other))
+}
+
+/// The default permission set granted to a fresh account.
+///
+/// Falls back to the built-in default set if [`init`] was never called.
+#[inline]
+pub fn default_set() -> HashSet<Permission> {
+    DEFAULT_SET.get().cloned().unwrap_or_else(|| {
+        default_default_grants()
+            .iter()
+            .flat_map(|g| resolve_grant(g))
+            .collect()
+    })
+}
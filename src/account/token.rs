@@ -0,0 +1,142 @@
+//! Scoped application tokens ("API keys").
+//!
+//! An application token lets an account authenticate non-interactively,
+//! without its password, scoped to a subset of the permissions it holds at
+//! mint time. Scopes are re-checked against the account's *live*
+//! permissions on every verification, so revoking a permission from the
+//! account immediately narrows every token minted from it.
+
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+
+use argon2::{password_hash::PasswordVerifier, Argon2, PasswordHash};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use super::Permission;
+use crate::{config, Error};
+
+/// The length, in bytes, of a minted token's opaque id and plaintext
+/// secret.
+const ID_LEN: usize = 16;
+const SECRET_LEN: usize = 32;
+
+/// An application token record, as stored in [`super::Ext`].
+///
+/// The plaintext secret is never stored: only its Argon2id hash is, so a
+/// leaked data store doesn't expose usable credentials.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// The opaque, hex-encoded id presented alongside the secret.
+    id: String,
+    /// The Argon2id hash of the secret.
+    secret_hash: String,
+    /// The permissions this token may use, a subset of the account's own
+    /// permissions at mint time.
+    scope: HashSet<Permission>,
+    /// When this token stops being valid, if it expires.
+    expires_at: Option<SystemTime>,
+    /// When this token was last successfully verified.
+    last_used: Option<SystemTime>,
+}
+
+impl ApiKey {
+    /// The opaque id of this token.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The permissions this token was scoped to at mint time.
+    #[inline]
+    pub fn scope(&self) -> &HashSet<Permission> {
+        &self.scope
+    }
+
+    /// When this token stops being valid, if it expires.
+    #[inline]
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// When this token was last successfully verified.
+    #[inline]
+    pub fn last_used(&self) -> Option<SystemTime> {
+        self.last_used
+    }
+
+    /// Whether this token has expired as of `now`.
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|exp| now >= exp)
+    }
+}
+
+/// Generates a random, hex-encoded identifier of `len` bytes.
+fn gen_hex(len: usize) -> String {
+    let mut buf = vec![0; len];
+    rand::thread_rng().fill_bytes(&mut buf);
+    buf.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Mints a new token: generates its id and plaintext secret, hashes the
+/// secret with Argon2id using `kdf`'s cost parameters, and returns both the
+/// [`ApiKey`] record to store and the plaintext secret to hand back to the
+/// caller once.
+///
+/// # Errors
+///
+/// Errors if hashing the secret failed.
+pub(super) fn mint(
+    scope: HashSet<Permission>,
+    expires_at: Option<SystemTime>,
+    kdf: &config::Kdf,
+) -> Result<(ApiKey, String), Error> {
+    let id = gen_hex(ID_LEN);
+    let secret = gen_hex(SECRET_LEN);
+    let secret_hash = super::hash_password(kdf, &secret)?;
+    Ok((
+        ApiKey {
+            id,
+            secret_hash,
+            scope,
+            expires_at,
+            last_used: None,
+        },
+        secret,
+    ))
+}
+
+/// Verifies `secret` against `key`, returning `true` if it matches and the
+/// token hasn't expired.
+///
+/// The Argon2 comparison always runs before the expiry check, so timing
+/// doesn't distinguish an expired token from one with a wrong secret.
+pub(super) fn verify(key: &ApiKey, secret: &str) -> bool {
+    let matches = PasswordHash::new(&key.secret_hash)
+        .is_ok_and(|hash| Argon2::default().verify_password(secret.as_bytes(), &hash).is_ok());
+    matches && !key.is_expired(SystemTime::now())
+}
+
+/// Runs a single Argon2id hash of `secret` with `kdf`'s cost parameters,
+/// the same amount of work as the one Argon2 comparison in [`verify`], so
+/// that looking up a token id that doesn't exist takes comparable time to
+/// checking a wrong secret against one that does. Hashing with `kdf`, rather
+/// than a fixed or default cost, matters precisely because the cost is
+/// operator-configured: anything else reopens the timing gap the
+/// configuration was meant to close.
+pub(super) fn verify_dummy(secret: &str, kdf: &config::Kdf) {
+    let _ = super::hash_password(kdf, secret);
+}
+
+/// Records that `key` was just used successfully.
+pub(super) fn mark_used(key: &mut ApiKey) {
+    key.last_used = Some(SystemTime::now());
+}
+
+/// The point in time `duration` from now, for callers building an expiry.
+#[inline]
+pub fn expires_in(duration: Duration) -> SystemTime {
+    SystemTime::now() + duration
+}
@@ -0,0 +1,153 @@
+//! Verify sessions.
+//!
+//! A verify session is a short-lived, captcha-protected action that requires
+//! confirming control of an email address, such as resetting a password.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, SystemTime},
+};
+
+use lettre::{message::Mailbox, transport::smtp, AsyncSmtpTransport, AsyncTransport, Message};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, Error, TestCx};
+
+/// The minimal interval between two requests of the same verify session.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// A 6-digit captcha code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Captcha([u8; 6]);
+
+impl Captcha {
+    /// Generates a new random captcha.
+    fn gen() -> Self {
+        let mut buf = [0; 6];
+        let mut rng = rand::thread_rng();
+        for b in &mut buf {
+            *b = rng.gen_range(b'0'..=b'9');
+        }
+        Self(buf)
+    }
+}
+
+impl Display for Captcha {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // SAFETY: every byte is within `b'0'..=b'9'`.
+        f.write_str(unsafe { std::str::from_utf8_unchecked(&self.0) })
+    }
+}
+
+/// The variant of a verify session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VerifyVariant {
+    /// Resetting password.
+    ResetPassword,
+    /// Changing the login email to a new, unverified address.
+    ChangeEmail,
+    /// A second factor required to complete a login.
+    LoginTwoFactor,
+}
+
+impl VerifyVariant {
+    /// The subject line used when sending the captcha email for this variant.
+    fn subject(&self) -> &'static str {
+        match self {
+            Self::ResetPassword => "reset password",
+            Self::ChangeEmail => "change email",
+            Self::LoginTwoFactor => "login verification code",
+        }
+    }
+}
+
+impl Display for VerifyVariant {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.subject())
+    }
+}
+
+/// A verify session, holding the current captcha and rate-limiting state.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyCx {
+    /// The current captcha of this session.
+    captcha: Captcha,
+    /// The time the captcha was last sent.
+    last_req: SystemTime,
+}
+
+impl VerifyCx {
+    /// Creates a new verify session, ready to send its first captcha.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            captcha: Captcha::gen(),
+            last_req: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    /// The captcha of this session.
+    #[inline]
+    pub fn captcha(&self) -> Captcha {
+        self.captcha
+    }
+
+    /// Regenerates the captcha, enforcing the minimal interval between
+    /// two requests.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the difference between the last request time and the
+    /// current time is no more than 10 minutes.
+    pub fn update(&mut self) -> Result<(), Error> {
+        let now = SystemTime::now();
+        if now
+            .duration_since(self.last_req)
+            .map_or(false, |d| d <= REQUEST_INTERVAL)
+        {
+            return Err(Error::TooManyRequests);
+        }
+        self.captcha = Captcha::gen();
+        self.last_req = now;
+        Ok(())
+    }
+
+    /// Sends the current captcha to `to`, with `subject` after an `SMS4 - `
+    /// prefix as the email subject, and records the send time.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the email send failed.
+    pub async fn send_email<E>(
+        &mut self,
+        config: &config::SMTP,
+        to: Mailbox,
+        subject: impl Display,
+        transport: &AsyncSmtpTransport<E>,
+        cx: &TestCx,
+    ) -> Result<(), Error>
+    where
+        E: lettre::Executor,
+        AsyncSmtpTransport<E>: lettre::AsyncTransport<Error = smtp::Error>,
+    {
+        let _ = cx;
+        let email = Message::builder()
+            .from(config.from.clone())
+            .to(to)
+            .subject(format!("SMS4 - {subject}"))
+            .body(format!("Your captcha is: {}", self.captcha))?;
+        transport.send(email).await?;
+        self.last_req = SystemTime::now();
+        Ok(())
+    }
+}
+
+impl Default for VerifyCx {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}